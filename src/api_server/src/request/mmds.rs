@@ -1,18 +1,209 @@
 // Copyright 2019 Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::HashMap;
+
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use base64::Engine;
+use ring::signature::{self, UnparsedPublicKey, VerificationAlgorithm};
+
 use crate::parsed_request::{Error, ParsedRequest, RequestAction};
 use crate::request::Body;
 use logger::{IncMetric, METRICS};
 use micro_http::StatusCode;
-use mmds::data_store::MmdsVersionType;
+use mmds::data_store::{
+    JsonPatchOp, JsonPatchOperation, MmdsOutputFormat, MmdsPatchDocument, MmdsVersionType,
+};
 use vmm::rpc_interface::VmmAction::SetMmdsConfiguration;
+use vmm::vmm_config::mmds::{MmdsConfig, MmdsSignatureAuthConfig};
+
+/// Request metadata needed to parse a PUT/PATCH `/mmds` request against the MMDS
+/// configuration currently in effect: the method and path (for the synthetic
+/// `(request-target)` signing-string component), a lowercased header map, and the
+/// effective `MmdsConfig` itself, so the data-size limit and signature-auth policy are
+/// read from the single copy the VMM owns rather than duplicated here.
+pub(crate) struct MmdsRequestContext<'a> {
+    pub method: &'a str,
+    pub path: &'a str,
+    pub headers: &'a HashMap<String, String>,
+    pub config: &'a MmdsConfig,
+}
+
+/// Upper bound on the size of a PUT/PATCH `/mmds` body, in bytes, used until a
+/// `max_size_bytes` value is configured through `PUT /mmds/config`.
+const DEFAULT_MAX_MMDS_DATA_SIZE: usize = 51200;
+
+/// Rejects bodies larger than the MMDS data-size limit configured on `ctx`, before
+/// they're deserialized and held by the metadata store.
+fn validate_data_size(ctx: &MmdsRequestContext, body: &Body) -> Result<(), Error> {
+    let limit = ctx.config.max_size_bytes.unwrap_or(DEFAULT_MAX_MMDS_DATA_SIZE);
+    check_data_size(body.raw().len(), limit)
+}
+
+fn check_data_size(len: usize, limit: usize) -> Result<(), Error> {
+    if len > limit {
+        return Err(Error::Generic(
+            StatusCode::PayloadTooLarge,
+            format!(
+                "The MMDS data store size limit of {} bytes was exceeded by a body of {} bytes.",
+                limit, len
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Verifies the request's `Signature` header against the MMDS authentication policy
+/// configured on `ctx`, if one is set. A no-op when authentication hasn't been
+/// configured, so existing setups that trust their MMDS socket are unaffected.
+fn verify_mmds_signature(ctx: &MmdsRequestContext, body: &Body) -> Result<(), Error> {
+    let auth = match ctx.config.signature_auth.as_ref() {
+        Some(auth) => auth,
+        None => return Ok(()),
+    };
+
+    let signature_header = ctx.headers.get("signature").ok_or_else(|| {
+        Error::Generic(
+            StatusCode::Unauthorized,
+            "Missing `Signature` header on authenticated MMDS request.".to_string(),
+        )
+    })?;
+    let parsed = parse_signature_header(signature_header)?;
+
+    // A client could otherwise sign a minimal, easily-replayed header set and still
+    // satisfy an operator that requires e.g. `date` and `digest` to be covered.
+    for required in &auth.required_headers {
+        if !parsed.headers.iter().any(|h| h.eq_ignore_ascii_case(required)) {
+            return Err(Error::Generic(
+                StatusCode::Forbidden,
+                format!("Required header `{}` is not covered by the signature.", required),
+            ));
+        }
+    }
+
+    if let Some(digest_header) = ctx.headers.get("digest") {
+        let expected = format!(
+            "SHA-256={}",
+            BASE64_STANDARD.encode(ring::digest::digest(&ring::digest::SHA256, body.raw()))
+        );
+        if digest_header != &expected {
+            return Err(Error::Generic(
+                StatusCode::Unauthorized,
+                "`Digest` header does not match the request body.".to_string(),
+            ));
+        }
+    }
+
+    let signing_string = build_signing_string(ctx, &parsed.headers)?;
+
+    let signature_bytes = BASE64_STANDARD.decode(parsed.signature_b64).map_err(|_| {
+        Error::Generic(
+            StatusCode::Unauthorized,
+            "Malformed `signature` parameter in `Signature` header.".to_string(),
+        )
+    })?;
+    let public_key_bytes = BASE64_STANDARD.decode(&auth.public_key).map_err(|_| {
+        Error::Generic(
+            StatusCode::Unauthorized,
+            "The configured MMDS public key is not valid base64.".to_string(),
+        )
+    })?;
+    let algorithm: &dyn VerificationAlgorithm = match parsed.algorithm {
+        "ed25519" => &signature::ED25519,
+        "rsa-sha256" => &signature::RSA_PKCS1_2048_8192_SHA256,
+        "ecdsa-sha256" => &signature::ECDSA_P256_SHA256_ASN1,
+        other => {
+            return Err(Error::Generic(
+                StatusCode::Unauthorized,
+                format!("Unsupported signature algorithm `{}`.", other),
+            ));
+        }
+    };
+
+    UnparsedPublicKey::new(algorithm, &public_key_bytes)
+        .verify(signing_string.as_bytes(), &signature_bytes)
+        .map_err(|_| {
+            Error::Generic(
+                StatusCode::Unauthorized,
+                "MMDS request signature verification failed.".to_string(),
+            )
+        })
+}
 
-pub(crate) fn parse_get_mmds(path_seconds_token: Option<&&str>) -> Result<ParsedRequest, Error> {
+/// The parameters of an HTTP `Signature` header (draft-cavage-http-signatures).
+struct ParsedSignature<'a> {
+    algorithm: &'a str,
+    headers: Vec<&'a str>,
+    signature_b64: &'a str,
+}
+
+fn parse_signature_header(value: &str) -> Result<ParsedSignature<'_>, Error> {
+    let malformed = || {
+        Error::Generic(
+            StatusCode::BadRequest,
+            "Malformed `Signature` header.".to_string(),
+        )
+    };
+
+    let mut algorithm = None;
+    let mut headers = None;
+    let mut signature_b64 = None;
+    for param in value.split(',') {
+        let mut parts = param.splitn(2, '=');
+        let key = parts.next().ok_or_else(malformed)?.trim();
+        let raw_value = parts.next().ok_or_else(malformed)?.trim().trim_matches('"');
+        match key {
+            "algorithm" => algorithm = Some(raw_value),
+            "headers" => headers = Some(raw_value.split_whitespace().collect()),
+            "signature" => signature_b64 = Some(raw_value),
+            // `keyId` identifies which key to use; this MMDS only ever trusts one
+            // configured key, so the value itself doesn't affect verification.
+            _ => {}
+        }
+    }
+
+    Ok(ParsedSignature {
+        algorithm: algorithm.ok_or_else(malformed)?,
+        headers: headers.ok_or_else(malformed)?,
+        signature_b64: signature_b64.ok_or_else(malformed)?,
+    })
+}
+
+/// Reconstructs the draft-cavage-http-signatures signing string: one `name: value`
+/// line per header the signature covers, with `(request-target)` rendered as the
+/// lowercased method and the request path.
+fn build_signing_string(ctx: &MmdsRequestContext, headers: &[&str]) -> Result<String, Error> {
+    let mut lines = Vec::with_capacity(headers.len());
+    for header in headers {
+        if header.eq_ignore_ascii_case("(request-target)") {
+            lines.push(format!(
+                "(request-target): {} {}",
+                ctx.method.to_ascii_lowercase(),
+                ctx.path
+            ));
+            continue;
+        }
+        let name = header.to_ascii_lowercase();
+        let value = ctx.headers.get(&name).ok_or_else(|| {
+            Error::Generic(
+                StatusCode::Unauthorized,
+                format!("Signed header `{}` is missing from the request.", name),
+            )
+        })?;
+        lines.push(format!("{}: {}", name, value));
+    }
+    Ok(lines.join("\n"))
+}
+
+pub(crate) fn parse_get_mmds(
+    path_seconds_token: Option<&&str>,
+    accept_header: Option<&str>,
+) -> Result<ParsedRequest, Error> {
     match path_seconds_token {
         None => {
             METRICS.get_api_requests.mmds_count.inc();
-            Ok(ParsedRequest::new(RequestAction::GetMMDS))
+            let format = parse_accept_header(accept_header)?;
+            Ok(ParsedRequest::new(RequestAction::GetMMDS(format)))
         }
         Some(&"version") => Ok(ParsedRequest::new(RequestAction::GetMMDSVersion)),
         Some(&unrecognized) => Err(Error::Generic(
@@ -22,30 +213,251 @@ pub(crate) fn parse_get_mmds(path_seconds_token: Option<&&str>) -> Result<Parsed
     }
 }
 
+/// Picks the MMDS response representation based on the request's `Accept` header.
+///
+/// Absent header or a wildcard `*/*` default to `application/json`, matching the
+/// pre-existing behavior. `Accept` can list several comma-separated media types, each
+/// optionally carrying a `;q=` quality value (RFC 7231 §5.3.2, default `1`); the
+/// highest-weighted *supported* media type wins, with ties broken by listed order.
+/// `text/plain` selects the EC2 IMDS-style flattened rendering. A header listing no
+/// supported media type is rejected, since the data store has no renderer for it.
+fn parse_accept_header(accept_header: Option<&str>) -> Result<MmdsOutputFormat, Error> {
+    let header = match accept_header.map(str::trim) {
+        None | Some("") => return Ok(MmdsOutputFormat::Json),
+        Some(header) => header,
+    };
+
+    let mut best: Option<(f32, MmdsOutputFormat)> = None;
+    for media_type in header.split(',') {
+        let mut params = media_type.split(';');
+        let format = match params.next().unwrap_or("").trim() {
+            "*/*" | "application/json" => MmdsOutputFormat::Json,
+            "text/plain" => MmdsOutputFormat::Imds,
+            _ => continue,
+        };
+        let quality = params
+            .filter_map(|param| param.trim().strip_prefix("q="))
+            .next()
+            .and_then(|q| q.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+
+        // `q=0` means "not acceptable" (RFC 7231 §5.3.1), not merely lowest-priority.
+        if quality == 0.0 {
+            continue;
+        }
+
+        if best.as_ref().map_or(true, |(best_quality, _)| quality > *best_quality) {
+            best = Some((quality, format));
+        }
+    }
+
+    best.map(|(_, format)| format).ok_or_else(|| {
+        Error::Generic(
+            StatusCode::NotAcceptable,
+            format!("Unsupported media type in `Accept` header: `{}`.", header),
+        )
+    })
+}
+
+/// Converts a PUT `/mmds` body into the JSON document the data store expects, based
+/// on the request's `Content-Type`.
+///
+/// A missing or `application/json` content type keeps the pre-existing behavior of
+/// parsing the body as a raw JSON object. `application/x-www-form-urlencoded` and
+/// `multipart/form-data` bodies are decoded into an equivalent flat JSON object so
+/// callers don't have to re-encode form submissions as JSON. Any other content type
+/// also falls back to the JSON parser, same as before this was content-type aware.
+fn mmds_body_to_json(body: &Body, content_type: Option<&str>) -> Result<serde_json::Value, Error> {
+    let media_type = content_type.map(|ct| ct.split(';').next().unwrap_or(ct).trim());
+
+    match media_type {
+        Some("application/x-www-form-urlencoded") => parse_form_urlencoded(body.raw()),
+        Some("multipart/form-data") => {
+            let boundary = extract_multipart_boundary(content_type.unwrap_or_default())?;
+            parse_multipart_form_data(body.raw(), boundary)
+        }
+        _ => serde_json::from_slice(body.raw()).map_err(Error::SerdeJson),
+    }
+}
+
+/// Percent-decodes a `application/x-www-form-urlencoded` component, turning `+` into
+/// a space and `%XX` escapes into the byte they encode.
+fn percent_decode(component: &str) -> Result<String, Error> {
+    let malformed = || {
+        Error::Generic(
+            StatusCode::BadRequest,
+            "Malformed percent-encoding in MMDS form body.".to_string(),
+        )
+    };
+
+    let bytes = component.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3).ok_or_else(malformed)?;
+                let hex = std::str::from_utf8(hex).map_err(|_| malformed())?;
+                decoded.push(u8::from_str_radix(hex, 16).map_err(|_| malformed())?);
+                i += 3;
+            }
+            b => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| malformed())
+}
+
+/// Decodes an `application/x-www-form-urlencoded` body into a flat JSON object, one
+/// key per `key=value` pair.
+fn parse_form_urlencoded(raw: &[u8]) -> Result<serde_json::Value, Error> {
+    let body = std::str::from_utf8(raw).map_err(|_| {
+        Error::Generic(
+            StatusCode::BadRequest,
+            "MMDS form body is not valid UTF-8.".to_string(),
+        )
+    })?;
+
+    let mut fields = serde_json::Map::new();
+    if !body.is_empty() {
+        for pair in body.split('&') {
+            let mut parts = pair.splitn(2, '=');
+            let key = percent_decode(parts.next().unwrap_or(""))?;
+            let value = percent_decode(parts.next().unwrap_or(""))?;
+            fields.insert(key, serde_json::Value::String(value));
+        }
+    }
+    Ok(serde_json::Value::Object(fields))
+}
+
+/// Pulls the `boundary` parameter out of a `multipart/form-data` content type.
+fn extract_multipart_boundary(content_type: &str) -> Result<&str, Error> {
+    content_type
+        .split(';')
+        .skip(1)
+        .map(str::trim)
+        .find_map(|param| param.strip_prefix("boundary="))
+        .map(|boundary| boundary.trim_matches('"'))
+        .ok_or_else(|| {
+            Error::Generic(
+                StatusCode::BadRequest,
+                "Missing `boundary` parameter in multipart/form-data Content-Type.".to_string(),
+            )
+        })
+}
+
+/// Decodes a `multipart/form-data` body into a flat JSON object, one key per part's
+/// `name`, with the part's body stored as a UTF-8 string value.
+fn parse_multipart_form_data(raw: &[u8], boundary: &str) -> Result<serde_json::Value, Error> {
+    let malformed = || {
+        Error::Generic(
+            StatusCode::BadRequest,
+            "Malformed multipart/form-data body.".to_string(),
+        )
+    };
+
+    let body = std::str::from_utf8(raw).map_err(|_| malformed())?;
+    let delimiter = format!("--{}", boundary);
+
+    let mut fields = serde_json::Map::new();
+    for part in body.split(&delimiter) {
+        let part = part.trim_start_matches("\r\n");
+        // The segment before the first delimiter is empty, and the segment after the
+        // closing `--{boundary}--` delimiter is the terminal `--` marker; neither
+        // carries a part and both should be skipped rather than treated as malformed.
+        // Checked on a copy with the trailing CRLF trimmed off too, since that CRLF
+        // belongs to the next delimiter, not the part content.
+        if part.trim_end_matches("\r\n").is_empty() || part.trim_end_matches("\r\n") == "--" {
+            continue;
+        }
+
+        // Split before trimming the part's own trailing CRLF away, so a part whose
+        // body is empty (header block immediately followed by the next delimiter)
+        // still has a `\r\n\r\n` separator left to split on.
+        let mut sections = part.splitn(2, "\r\n\r\n");
+        let headers = sections.next().ok_or_else(malformed)?;
+        let content = sections.next().ok_or_else(malformed)?;
+        let content = content.strip_suffix("\r\n").unwrap_or(content);
+
+        let name = headers
+            .lines()
+            .find_map(|line| {
+                let line = line.trim();
+                if !line.to_ascii_lowercase().starts_with("content-disposition:") {
+                    return None;
+                }
+                line.split(';').find_map(|param| {
+                    param
+                        .trim()
+                        .strip_prefix("name=")
+                        .map(|name| name.trim_matches('"').to_string())
+                })
+            })
+            .ok_or_else(malformed)?;
+
+        fields.insert(name, serde_json::Value::String(content.to_string()));
+    }
+
+    if fields.is_empty() {
+        return Err(malformed());
+    }
+
+    Ok(serde_json::Value::Object(fields))
+}
+
 pub(crate) fn parse_put_mmds(
     body: &Body,
     path_second_token: Option<&&str>,
+    content_type: Option<&str>,
+    request_context: &MmdsRequestContext,
 ) -> Result<ParsedRequest, Error> {
     METRICS.put_api_requests.mmds_count.inc();
     match path_second_token {
-        None => Ok(ParsedRequest::new(RequestAction::PutMMDS(
-            serde_json::from_slice(body.raw()).map_err(|e| {
+        None => {
+            validate_data_size(request_context, body).map_err(|e| {
                 METRICS.put_api_requests.mmds_fails.inc();
-                Error::SerdeJson(e)
-            })?,
-        ))),
-        Some(&"config") => Ok(ParsedRequest::new_sync(SetMmdsConfiguration(
-            serde_json::from_slice(body.raw()).map_err(|e| {
+                e
+            })?;
+            verify_mmds_signature(request_context, body).map_err(|e| {
+                METRICS.put_api_requests.mmds_fails.inc();
+                e
+            })?;
+            let data = mmds_body_to_json(body, content_type).map_err(|e| {
+                METRICS.put_api_requests.mmds_fails.inc();
+                e
+            })?;
+            Ok(ParsedRequest::new(RequestAction::PutMMDS(data)))
+        }
+        Some(&"config") => {
+            // Reconfiguration is a mutation like any other: once signature auth is
+            // set up, changing (or clearing) it requires a valid signature too, so a
+            // request can't silently disable the control it's itself subject to.
+            verify_mmds_signature(request_context, body).map_err(|e| {
+                METRICS.put_api_requests.mmds_fails.inc();
+                e
+            })?;
+            let config: MmdsConfig = serde_json::from_slice(body.raw()).map_err(|e| {
                 METRICS.put_api_requests.mmds_fails.inc();
                 Error::SerdeJson(e)
-            })?,
-        ))),
+            })?;
+            Ok(ParsedRequest::new_sync(SetMmdsConfiguration(config)))
+        }
         Some(&"version") => {
             let version_type =
                 serde_json::from_slice::<MmdsVersionType>(body.raw()).map_err(|e| {
                     METRICS.put_api_requests.mmds_fails.inc();
                     Error::SerdeJson(e)
                 })?;
+            verify_mmds_signature(request_context, body).map_err(|e| {
+                METRICS.put_api_requests.mmds_fails.inc();
+                e
+            })?;
             Ok(ParsedRequest::new(RequestAction::SetMMDSVersion(
                 version_type.version(),
             )))
@@ -60,32 +472,186 @@ pub(crate) fn parse_put_mmds(
     }
 }
 
-pub(crate) fn parse_patch_mmds(body: &Body) -> Result<ParsedRequest, Error> {
+pub(crate) fn parse_patch_mmds(
+    body: &Body,
+    content_type: Option<&str>,
+    request_context: &MmdsRequestContext,
+) -> Result<ParsedRequest, Error> {
     METRICS.patch_api_requests.mmds_count.inc();
-    Ok(ParsedRequest::new(RequestAction::PatchMMDS(
-        serde_json::from_slice(body.raw()).map_err(|e| {
+    validate_data_size(request_context, body).map_err(|e| {
+        METRICS.patch_api_requests.mmds_fails.inc();
+        e
+    })?;
+    verify_mmds_signature(request_context, body).map_err(|e| {
+        METRICS.patch_api_requests.mmds_fails.inc();
+        e
+    })?;
+
+    let media_type = content_type.map(|ct| ct.split(';').next().unwrap_or(ct).trim());
+    let patch_document = match media_type {
+        Some("application/json-patch+json") => {
+            let ops: Vec<JsonPatchOperation> = serde_json::from_slice(body.raw()).map_err(|e| {
+                METRICS.patch_api_requests.mmds_fails.inc();
+                Error::SerdeJson(e)
+            })?;
+            validate_json_patch_ops(&ops).map_err(|e| {
+                METRICS.patch_api_requests.mmds_fails.inc();
+                e
+            })?;
+            MmdsPatchDocument::JsonPatch(ops)
+        }
+        // RFC 7386 merge patch, the pre-existing behavior.
+        _ => MmdsPatchDocument::MergePatch(serde_json::from_slice(body.raw()).map_err(|e| {
             METRICS.patch_api_requests.mmds_fails.inc();
             Error::SerdeJson(e)
-        })?,
-    )))
+        })?),
+    };
+
+    Ok(ParsedRequest::new(RequestAction::PatchMMDS(patch_document)))
+}
+
+/// Structural validation of an RFC 6902 JSON Patch document, run before it's handed
+/// off to the data store. Each operation must carry the members its `op` requires and
+/// every JSON Pointer must be well-formed; the store applies the operations
+/// sequentially against a clone of the stored document and only commits if every one
+/// succeeds (so e.g. a `test` mismatch aborts the whole patch there, not here).
+fn validate_json_patch_ops(ops: &[JsonPatchOperation]) -> Result<(), Error> {
+    for op in ops {
+        if !is_valid_json_pointer(&op.path) {
+            return Err(Error::Generic(
+                StatusCode::BadRequest,
+                format!("Invalid JSON Pointer `{}` in JSON Patch operation.", op.path),
+            ));
+        }
+
+        match op.op {
+            JsonPatchOp::Add | JsonPatchOp::Replace | JsonPatchOp::Test => {
+                if op.value.is_none() {
+                    return Err(Error::Generic(
+                        StatusCode::BadRequest,
+                        format!(
+                            "JSON Patch `{:?}` operation at `{}` is missing `value`.",
+                            op.op, op.path
+                        ),
+                    ));
+                }
+            }
+            JsonPatchOp::Move | JsonPatchOp::Copy => match &op.from {
+                Some(from) if is_valid_json_pointer(from) => {}
+                Some(from) => {
+                    return Err(Error::Generic(
+                        StatusCode::BadRequest,
+                        format!("Invalid JSON Pointer `{}` in `from` member.", from),
+                    ));
+                }
+                None => {
+                    return Err(Error::Generic(
+                        StatusCode::BadRequest,
+                        format!(
+                            "JSON Patch `{:?}` operation at `{}` is missing `from`.",
+                            op.op, op.path
+                        ),
+                    ));
+                }
+            },
+            JsonPatchOp::Remove => {}
+        }
+    }
+    Ok(())
+}
+
+/// A JSON Pointer (RFC 6901) is either empty (the whole document) or starts with `/`.
+fn is_valid_json_pointer(path: &str) -> bool {
+    path.is_empty() || path.starts_with('/')
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// A request context with no headers and the default MMDS configuration, for
+    /// tests that don't configure a data-size limit or signature authentication.
+    fn unauthenticated_context() -> MmdsRequestContext<'static> {
+        static HEADERS: std::sync::OnceLock<HashMap<String, String>> = std::sync::OnceLock::new();
+        static CONFIG: std::sync::OnceLock<MmdsConfig> = std::sync::OnceLock::new();
+        MmdsRequestContext {
+            method: "PUT",
+            path: "/mmds",
+            headers: HEADERS.get_or_init(HashMap::new),
+            config: CONFIG.get_or_init(MmdsConfig::default),
+        }
+    }
+
     #[test]
     fn test_parse_get_mmds_request() {
         // Requests to `/mmds`.
-        assert!(parse_get_mmds(None).is_ok());
+        assert!(parse_get_mmds(None, None).is_ok());
         assert!(METRICS.get_api_requests.mmds_count.count() > 0);
 
         // Requests to `/mmds/version`.
         let path = "version";
-        assert!(parse_get_mmds(Some(&path)).is_ok());
+        assert!(parse_get_mmds(Some(&path), None).is_ok());
 
         // Requests to invalid path.
-        assert!(parse_get_mmds(Some(&"invalid_path")).is_err());
+        assert!(parse_get_mmds(Some(&"invalid_path"), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_get_mmds_accept_header() {
+        // No `Accept` header and the wildcard both default to JSON.
+        assert!(parse_get_mmds(None, None).is_ok());
+        assert!(parse_get_mmds(None, Some("*/*")).is_ok());
+
+        // Explicit JSON and IMDS-style plaintext are both accepted.
+        assert!(parse_get_mmds(None, Some("application/json")).is_ok());
+        assert!(parse_get_mmds(None, Some("text/plain")).is_ok());
+
+        // A `q` parameter or other list members don't prevent a match.
+        assert!(parse_get_mmds(None, Some("text/plain;q=0.9")).is_ok());
+        assert!(parse_get_mmds(None, Some("application/xml, text/plain")).is_ok());
+
+        // Unsupported media types are rejected with `406 Not Acceptable`.
+        match parse_get_mmds(None, Some("application/xml")) {
+            Err(Error::Generic(StatusCode::NotAcceptable, _)) => (),
+            _ => panic!("Expected a 406 Not Acceptable error."),
+        }
+    }
+
+    #[test]
+    fn test_parse_accept_header_quality_values() {
+        // The higher-weighted type wins even when listed second.
+        assert_eq!(
+            parse_accept_header(Some("application/json;q=0.1, text/plain;q=0.9")).unwrap(),
+            MmdsOutputFormat::Imds
+        );
+        assert_eq!(
+            parse_accept_header(Some("text/plain;q=0.1, application/json;q=0.9")).unwrap(),
+            MmdsOutputFormat::Json
+        );
+
+        // Equal (or default, unspecified) weights keep the first-listed type.
+        assert_eq!(
+            parse_accept_header(Some("application/json, text/plain")).unwrap(),
+            MmdsOutputFormat::Json
+        );
+        assert_eq!(
+            parse_accept_header(Some("text/plain;q=0.5, application/json;q=0.5")).unwrap(),
+            MmdsOutputFormat::Imds
+        );
+
+        // `q=0` marks a type as explicitly not acceptable, not merely low-priority.
+        match parse_accept_header(Some("text/plain;q=0")) {
+            Err(Error::Generic(StatusCode::NotAcceptable, _)) => (),
+            other => panic!("Expected a 406 Not Acceptable error, got {:?}", other),
+        }
+        match parse_accept_header(Some("application/json;q=0")) {
+            Err(Error::Generic(StatusCode::NotAcceptable, _)) => (),
+            other => panic!("Expected a 406 Not Acceptable error, got {:?}", other),
+        }
+        assert_eq!(
+            parse_accept_header(Some("application/json;q=0, text/plain;q=0.5")).unwrap(),
+            MmdsOutputFormat::Imds
+        );
     }
 
     #[test]
@@ -93,10 +659,10 @@ mod tests {
         let body = r#"{
                 "foo": "bar"
               }"#;
-        assert!(parse_put_mmds(&Body::new(body), None).is_ok());
+        assert!(parse_put_mmds(&Body::new(body), None, None, &unauthenticated_context()).is_ok());
 
         let invalid_body = "invalid_body";
-        assert!(parse_put_mmds(&Body::new(invalid_body), None).is_err());
+        assert!(parse_put_mmds(&Body::new(invalid_body), None, None, &unauthenticated_context()).is_err());
         assert!(METRICS.put_api_requests.mmds_fails.count() > 0);
 
         // Test `config` path.
@@ -104,46 +670,46 @@ mod tests {
                 "ipv4_address": "169.254.170.2"
               }"#;
         let config_path = "config";
-        assert!(parse_put_mmds(&Body::new(body), Some(&config_path)).is_ok());
+        assert!(parse_put_mmds(&Body::new(body), Some(&config_path), None, &unauthenticated_context()).is_ok());
 
         let body = r#"{
                 "ipv4_address": ""
               }"#;
-        assert!(parse_put_mmds(&Body::new(body), Some(&config_path)).is_err());
+        assert!(parse_put_mmds(&Body::new(body), Some(&config_path), None, &unauthenticated_context()).is_err());
 
         // Equivalent to reset the mmds configuration.
         let empty_body = r#"{}"#;
-        assert!(parse_put_mmds(&Body::new(empty_body), Some(&config_path)).is_ok());
+        assert!(parse_put_mmds(&Body::new(empty_body), Some(&config_path), None, &unauthenticated_context()).is_ok());
 
         // Test `version` path.
         let version_path = "version";
         let body = r#"{
                 "version": "V1"
               }"#;
-        assert!(parse_put_mmds(&Body::new(body), Some(&version_path)).is_ok());
+        assert!(parse_put_mmds(&Body::new(body), Some(&version_path), None, &unauthenticated_context()).is_ok());
 
         let body = r#"{
                 "version": "V2"
               }"#;
-        assert!(parse_put_mmds(&Body::new(body), Some(&version_path)).is_ok());
+        assert!(parse_put_mmds(&Body::new(body), Some(&version_path), None, &unauthenticated_context()).is_ok());
         let body = r#"{
                 "version": "foo"
               }"#;
-        assert!(parse_put_mmds(&Body::new(body), Some(&version_path)).is_err());
+        assert!(parse_put_mmds(&Body::new(body), Some(&version_path), None, &unauthenticated_context()).is_err());
 
         let body = r#"{
                 "version": ""
               }"#;
-        assert!(parse_put_mmds(&Body::new(body), Some(&version_path)).is_err());
+        assert!(parse_put_mmds(&Body::new(body), Some(&version_path), None, &unauthenticated_context()).is_err());
 
         let invalid_config_body = r#"{
                 "invalid_config": "invalid_value"
               }"#;
-        assert!(parse_put_mmds(&Body::new(invalid_config_body), Some(&config_path)).is_err());
-        assert!(parse_put_mmds(&Body::new(invalid_config_body), Some(&version_path)).is_err());
-        assert!(parse_put_mmds(&Body::new(body), Some(&"invalid_path")).is_err());
-        assert!(parse_put_mmds(&Body::new(invalid_body), Some(&config_path)).is_err());
-        assert!(parse_put_mmds(&Body::new(invalid_body), Some(&version_path)).is_err());
+        assert!(parse_put_mmds(&Body::new(invalid_config_body), Some(&config_path), None, &unauthenticated_context()).is_err());
+        assert!(parse_put_mmds(&Body::new(invalid_config_body), Some(&version_path), None, &unauthenticated_context()).is_err());
+        assert!(parse_put_mmds(&Body::new(body), Some(&"invalid_path"), None, &unauthenticated_context()).is_err());
+        assert!(parse_put_mmds(&Body::new(invalid_body), Some(&config_path), None, &unauthenticated_context()).is_err());
+        assert!(parse_put_mmds(&Body::new(invalid_body), Some(&version_path), None, &unauthenticated_context()).is_err());
     }
 
     #[test]
@@ -151,9 +717,425 @@ mod tests {
         let body = r#"{
                 "foo": "bar"
               }"#;
-        assert!(parse_patch_mmds(&Body::new(body)).is_ok());
+        assert!(parse_patch_mmds(&Body::new(body), None, &unauthenticated_context()).is_ok());
         assert!(METRICS.patch_api_requests.mmds_count.count() > 0);
-        assert!(parse_patch_mmds(&Body::new("invalid_body")).is_err());
+        assert!(parse_patch_mmds(&Body::new("invalid_body"), None, &unauthenticated_context()).is_err());
         assert!(METRICS.patch_api_requests.mmds_fails.count() > 0);
     }
+
+    #[test]
+    fn test_parse_patch_mmds_json_patch() {
+        let body = r#"[
+                {"op": "add", "path": "/foo", "value": "bar"},
+                {"op": "test", "path": "/foo", "value": "bar"},
+                {"op": "remove", "path": "/baz"},
+                {"op": "copy", "from": "/foo", "path": "/qux"},
+                {"op": "move", "from": "/qux", "path": "/quux"},
+                {"op": "replace", "path": "/foo", "value": "updated"}
+              ]"#;
+        assert!(parse_patch_mmds(&Body::new(body), Some("application/json-patch+json"), &unauthenticated_context()).is_ok());
+
+        // Not a JSON array.
+        let not_an_array = r#"{"op": "add", "path": "/foo", "value": "bar"}"#;
+        assert!(
+            parse_patch_mmds(&Body::new(not_an_array), Some("application/json-patch+json"), &unauthenticated_context())
+                .is_err()
+        );
+
+        // `add` without `value`.
+        let missing_value = r#"[{"op": "add", "path": "/foo"}]"#;
+        assert!(
+            parse_patch_mmds(&Body::new(missing_value), Some("application/json-patch+json"), &unauthenticated_context())
+                .is_err()
+        );
+
+        // `move` without `from`.
+        let missing_from = r#"[{"op": "move", "path": "/foo"}]"#;
+        assert!(
+            parse_patch_mmds(&Body::new(missing_from), Some("application/json-patch+json"), &unauthenticated_context())
+                .is_err()
+        );
+
+        // Path that isn't a valid JSON Pointer.
+        let invalid_pointer = r#"[{"op": "remove", "path": "foo"}]"#;
+        assert!(
+            parse_patch_mmds(&Body::new(invalid_pointer), Some("application/json-patch+json"), &unauthenticated_context())
+                .is_err()
+        );
+
+        // A `charset` parameter alongside the media type doesn't change parsing.
+        assert!(parse_patch_mmds(
+            &Body::new(body),
+            Some("application/json-patch+json; charset=utf-8"),
+            &unauthenticated_context()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_check_data_size() {
+        assert!(check_data_size(8, 8).is_ok());
+        assert!(check_data_size(7, 8).is_ok());
+
+        match check_data_size(9, 8) {
+            Err(Error::Generic(StatusCode::PayloadTooLarge, _)) => (),
+            other => panic!("Expected a 413 Payload Too Large error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_mmds_data_size_limit_enforced_on_put_and_patch() {
+        // The default limit is generous enough that ordinary test bodies never trip
+        // it; this only exercises that both entry points consult it.
+        let oversized_body = "x".repeat(DEFAULT_MAX_MMDS_DATA_SIZE + 1);
+
+        match parse_put_mmds(&Body::new(&oversized_body), None, None, &unauthenticated_context()) {
+            Err(Error::Generic(StatusCode::PayloadTooLarge, _)) => (),
+            other => panic!("Expected a 413 Payload Too Large error, got {:?}", other),
+        }
+        match parse_patch_mmds(&Body::new(&oversized_body), None, &unauthenticated_context()) {
+            Err(Error::Generic(StatusCode::PayloadTooLarge, _)) => (),
+            other => panic!("Expected a 413 Payload Too Large error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_put_mmds_form_urlencoded() {
+        let body = "foo=bar&hello=world%21&empty=";
+        let parsed = parse_put_mmds(
+            &Body::new(body),
+            None,
+            Some("application/x-www-form-urlencoded"),
+            &unauthenticated_context(),
+        );
+        assert!(parsed.is_ok());
+
+        let value = mmds_body_to_json(
+            &Body::new(body),
+            Some("application/x-www-form-urlencoded"),
+        )
+        .unwrap();
+        assert_eq!(value["foo"], "bar");
+        assert_eq!(value["hello"], "world!");
+        assert_eq!(value["empty"], "");
+
+        // A `charset` parameter alongside the media type doesn't change parsing.
+        let value = mmds_body_to_json(
+            &Body::new("foo=bar"),
+            Some("application/x-www-form-urlencoded; charset=utf-8"),
+        )
+        .unwrap();
+        assert_eq!(value["foo"], "bar");
+
+        // Malformed percent-encoding is rejected.
+        assert!(mmds_body_to_json(
+            &Body::new("foo=%"),
+            Some("application/x-www-form-urlencoded")
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_put_mmds_multipart_form_data() {
+        let body = "--boundary123\r\n\
+                     Content-Disposition: form-data; name=\"foo\"\r\n\r\n\
+                     bar\r\n\
+                     --boundary123\r\n\
+                     Content-Disposition: form-data; name=\"hello\"\r\n\r\n\
+                     world\r\n\
+                     --boundary123--\r\n";
+        let content_type = "multipart/form-data; boundary=boundary123";
+
+        let parsed = parse_put_mmds(&Body::new(body), None, Some(content_type), &unauthenticated_context());
+        assert!(parsed.is_ok());
+
+        let value = mmds_body_to_json(&Body::new(body), Some(content_type)).unwrap();
+        assert_eq!(value["foo"], "bar");
+        assert_eq!(value["hello"], "world");
+
+        // Missing `boundary` parameter.
+        assert!(mmds_body_to_json(&Body::new(body), Some("multipart/form-data")).is_err());
+
+        // Malformed part (no `name` in its Content-Disposition header).
+        let malformed_body = "--boundary123\r\nContent-Disposition: form-data\r\n\r\nbar\r\n--boundary123--\r\n";
+        assert!(mmds_body_to_json(&Body::new(malformed_body), Some(content_type)).is_err());
+
+        // A part with an empty body is valid, mirroring the form-urlencoded `empty=` case.
+        let empty_field_body = "--boundary123\r\n\
+                     Content-Disposition: form-data; name=\"empty\"\r\n\r\n\
+                     \r\n\
+                     --boundary123--\r\n";
+        let value = mmds_body_to_json(&Body::new(empty_field_body), Some(content_type)).unwrap();
+        assert_eq!(value["empty"], "");
+    }
+
+    #[test]
+    fn test_validate_data_size_uses_configured_limit() {
+        // The limit comes from the caller-supplied `MmdsConfig` snapshot, not any
+        // state owned by this module, so two contexts can disagree without
+        // interfering with each other.
+        let headers = HashMap::new();
+        let config = MmdsConfig {
+            max_size_bytes: Some(8),
+            ..Default::default()
+        };
+        let ctx = MmdsRequestContext {
+            method: "PUT",
+            path: "/mmds",
+            headers: &headers,
+            config: &config,
+        };
+
+        assert!(validate_data_size(&ctx, &Body::new("12345678")).is_ok());
+        match validate_data_size(&ctx, &Body::new("123456789")) {
+            Err(Error::Generic(StatusCode::PayloadTooLarge, _)) => (),
+            other => panic!("Expected a 413 Payload Too Large error, got {:?}", other),
+        }
+
+        // No configured limit falls back to the default.
+        assert!(validate_data_size(&unauthenticated_context(), &Body::new("12345678")).is_ok());
+    }
+
+    fn signed_context<'a>(
+        key_pair: &ring::signature::Ed25519KeyPair,
+        method: &'a str,
+        path: &'a str,
+        headers: &'a HashMap<String, String>,
+        covered: &[&str],
+        config: &'a MmdsConfig,
+    ) -> String {
+        let ctx = MmdsRequestContext {
+            method,
+            path,
+            headers,
+            config,
+        };
+        let signing_string = build_signing_string(&ctx, covered).unwrap();
+        let signature = key_pair.sign(signing_string.as_bytes());
+        format!(
+            "keyId=\"mmds\",algorithm=\"ed25519\",headers=\"{}\",signature=\"{}\"",
+            covered.join(" "),
+            BASE64_STANDARD.encode(signature.as_ref())
+        )
+    }
+
+    fn signature_auth_config(
+        public_key: &ring::signature::Ed25519KeyPair,
+        required_headers: &[&str],
+    ) -> MmdsConfig {
+        use ring::signature::KeyPair;
+        MmdsConfig {
+            signature_auth: Some(MmdsSignatureAuthConfig {
+                public_key: BASE64_STANDARD.encode(public_key.public_key().as_ref()),
+                required_headers: required_headers.iter().map(|h| h.to_string()).collect(),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_mmds_signature_auth() {
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = ring::signature::Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let key_pair = ring::signature::Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let config = signature_auth_config(&key_pair, &["(request-target)", "date"]);
+
+        let body = r#"{"foo": "bar"}"#;
+
+        // No `Signature` header at all.
+        let headers = HashMap::new();
+        assert!(matches!(
+            parse_put_mmds(
+                &Body::new(body),
+                None,
+                None,
+                &MmdsRequestContext {
+                    method: "PUT",
+                    path: "/mmds",
+                    headers: &headers,
+                    config: &config,
+                },
+            ),
+            Err(Error::Generic(StatusCode::Unauthorized, _))
+        ));
+
+        // Signature present but doesn't cover a required header (`date`).
+        let mut headers = HashMap::new();
+        headers.insert(
+            "signature".to_string(),
+            signed_context(
+                &key_pair,
+                "PUT",
+                "/mmds",
+                &HashMap::new(),
+                &["(request-target)"],
+                &config,
+            ),
+        );
+        assert!(matches!(
+            parse_put_mmds(
+                &Body::new(body),
+                None,
+                None,
+                &MmdsRequestContext {
+                    method: "PUT",
+                    path: "/mmds",
+                    headers: &headers,
+                    config: &config,
+                },
+            ),
+            Err(Error::Generic(StatusCode::Forbidden, _))
+        ));
+
+        // Valid signature over the required headers succeeds.
+        let mut headers = HashMap::new();
+        headers.insert("date".to_string(), "Tue, 07 Jun 2014 20:51:35 GMT".to_string());
+        let signature = signed_context(
+            &key_pair,
+            "PUT",
+            "/mmds",
+            &headers,
+            &["(request-target)", "date"],
+            &config,
+        );
+        headers.insert("signature".to_string(), signature);
+        assert!(parse_put_mmds(
+            &Body::new(body),
+            None,
+            None,
+            &MmdsRequestContext {
+                method: "PUT",
+                path: "/mmds",
+                headers: &headers,
+                config: &config,
+            },
+        )
+        .is_ok());
+
+        // A `digest` header, when present, must match the hash of the body.
+        let mut tampered_headers = headers.clone();
+        tampered_headers.insert(
+            "digest".to_string(),
+            "SHA-256=not-the-real-digest".to_string(),
+        );
+        assert!(matches!(
+            parse_put_mmds(
+                &Body::new(body),
+                None,
+                None,
+                &MmdsRequestContext {
+                    method: "PUT",
+                    path: "/mmds",
+                    headers: &tampered_headers,
+                    config: &config,
+                },
+            ),
+            Err(Error::Generic(StatusCode::Unauthorized, _))
+        ));
+
+        // An unsupported algorithm is rejected outright.
+        let mut bad_algorithm_headers = headers.clone();
+        bad_algorithm_headers.insert(
+            "signature".to_string(),
+            "keyId=\"mmds\",algorithm=\"hmac-sha256\",headers=\"(request-target) date\",signature=\"AA==\"".to_string(),
+        );
+        assert!(matches!(
+            parse_put_mmds(
+                &Body::new(body),
+                None,
+                None,
+                &MmdsRequestContext {
+                    method: "PUT",
+                    path: "/mmds",
+                    headers: &bad_algorithm_headers,
+                    config: &config,
+                },
+            ),
+            Err(Error::Generic(StatusCode::Unauthorized, _))
+        ));
+
+        // A signature computed over the wrong path fails verification.
+        let mut wrong_path_headers = HashMap::new();
+        wrong_path_headers.insert("date".to_string(), "Tue, 07 Jun 2014 20:51:35 GMT".to_string());
+        let bad_signature = signed_context(
+            &key_pair,
+            "PUT",
+            "/mmds/other",
+            &wrong_path_headers,
+            &["(request-target)", "date"],
+            &config,
+        );
+        wrong_path_headers.insert("signature".to_string(), bad_signature);
+        assert!(matches!(
+            parse_put_mmds(
+                &Body::new(body),
+                None,
+                None,
+                &MmdsRequestContext {
+                    method: "PUT",
+                    path: "/mmds",
+                    headers: &wrong_path_headers,
+                    config: &config,
+                },
+            ),
+            Err(Error::Generic(StatusCode::Unauthorized, _))
+        ));
+    }
+
+    #[test]
+    fn test_mmds_config_path_requires_authentication_once_configured() {
+        // Once signature auth is configured, reconfiguring MMDS (including clearing
+        // the policy) requires a valid signature too, so an attacker can't silently
+        // disable authentication by PUTting a fresh, unsigned config.
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = ring::signature::Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let key_pair = ring::signature::Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let config = signature_auth_config(&key_pair, &["(request-target)"]);
+
+        let new_config_body = r#"{"ipv4_address": "169.254.170.2"}"#;
+        let config_path = "config";
+
+        // No `Signature` header: rejected.
+        let headers = HashMap::new();
+        assert!(matches!(
+            parse_put_mmds(
+                &Body::new(new_config_body),
+                Some(&config_path),
+                None,
+                &MmdsRequestContext {
+                    method: "PUT",
+                    path: "/mmds/config",
+                    headers: &headers,
+                    config: &config,
+                },
+            ),
+            Err(Error::Generic(StatusCode::Unauthorized, _))
+        ));
+
+        // A valid signature over the current policy is accepted.
+        let mut headers = HashMap::new();
+        headers.insert(
+            "signature".to_string(),
+            signed_context(
+                &key_pair,
+                "PUT",
+                "/mmds/config",
+                &HashMap::new(),
+                &["(request-target)"],
+                &config,
+            ),
+        );
+        assert!(parse_put_mmds(
+            &Body::new(new_config_body),
+            Some(&config_path),
+            None,
+            &MmdsRequestContext {
+                method: "PUT",
+                path: "/mmds/config",
+                headers: &headers,
+                config: &config,
+            },
+        )
+        .is_ok());
+    }
 }